@@ -0,0 +1,105 @@
+//! Development-mode shader hot-reloading, enabled by the `hot-reload`
+//! feature. Watches the GLSL source files on disk and relinks the fill
+//! program + rebuilds its PSO whenever they change, so shader tweaks (new
+//! gradient/AA math, say) can be iterated without restarting the renderer.
+//! On a compile failure the GLSL error is logged and the last good PSO is
+//! kept so the window stays open.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use gfx;
+use gfx::traits::FactoryExt;
+
+use render::{self, fill_pipeline};
+
+pub struct ShaderWatcher {
+    vertex_path: PathBuf,
+    fragment_path: PathBuf,
+    vertex_modified: Option<SystemTime>,
+    fragment_modified: Option<SystemTime>,
+}
+
+impl ShaderWatcher {
+    pub fn new(vertex_path: &str, fragment_path: &str) -> Self {
+        let mut watcher = ShaderWatcher {
+            vertex_path: PathBuf::from(vertex_path),
+            fragment_path: PathBuf::from(fragment_path),
+            vertex_modified: None,
+            fragment_modified: None,
+        };
+        // Prime the timestamps so the first `poll` doesn't immediately
+        // report a change.
+        watcher.vertex_modified = modified(&watcher.vertex_path);
+        watcher.fragment_modified = modified(&watcher.fragment_path);
+        watcher
+    }
+
+    /// Returns `true` if either shader file's mtime differs from the one
+    /// seen at the last call (including a file becoming unreadable), and
+    /// updates the stored timestamps.
+    pub fn poll_changed(&mut self) -> bool {
+        let vertex_now = modified(&self.vertex_path);
+        let fragment_now = modified(&self.fragment_path);
+
+        let changed = vertex_now != self.vertex_modified || fragment_now != self.fragment_modified;
+
+        self.vertex_modified = vertex_now;
+        self.fragment_modified = fragment_now;
+
+        changed
+    }
+
+    /// Attempts to relink the fill program and rebuild its PSO from the
+    /// current file contents. Leaves `pso` untouched and logs the
+    /// GLSL error on failure.
+    pub fn try_relink<F, R>(
+        &self,
+        factory: &mut F,
+        pso: &mut gfx::PipelineState<R, fill_pipeline::Meta>,
+    ) -> bool
+    where
+        F: gfx::Factory<R>,
+        R: gfx::Resources,
+    {
+        let (vertex_src, fragment_src) = match (fs::read(&self.vertex_path), fs::read(&self.fragment_path)) {
+            (Ok(v), Ok(f)) => (v, f),
+            _ => {
+                eprintln!("hot-reload: failed to read shader sources, keeping previous PSO");
+                return false;
+            }
+        };
+
+        let shader = match factory.link_program(&vertex_src, &fragment_src) {
+            Ok(shader) => shader,
+            Err(e) => {
+                eprintln!("hot-reload: shader relink failed, keeping previous PSO:\n{}", e);
+                return false;
+            }
+        };
+
+        let new_pso = factory.create_pipeline_from_program(
+            &shader,
+            gfx::Primitive::TriangleList,
+            render::fill_rasterizer(),
+            fill_pipeline::new(),
+        );
+
+        match new_pso {
+            Ok(new_pso) => {
+                *pso = new_pso;
+                println!("hot-reload: shader program reloaded");
+                true
+            }
+            Err(e) => {
+                eprintln!("hot-reload: pipeline rebuild failed, keeping previous PSO:\n{:?}", e);
+                false
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
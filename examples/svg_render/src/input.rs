@@ -0,0 +1,120 @@
+//! Unified pointer input: mouse drag/wheel and single-finger drag /
+//! two-finger pinch on touch-capable desktop windows all funnel through the
+//! same pan/zoom calls on `Scene`. (An Android port using this input layer
+//! is a tracked follow-up, not delivered here — see `main.rs`'s history.)
+
+use std::collections::HashMap;
+
+use svg_render::render::Scene;
+
+/// Cross-frame mouse/window state: the last known cursor position and
+/// window size (to convert pixel deltas/positions into world space) and
+/// whether the left button is currently held (drag).
+pub struct InputState {
+    pub cursor: (f64, f64),
+    pub window_size: (f64, f64),
+    pub dragging: bool,
+    touches: TouchTracker,
+}
+
+impl InputState {
+    pub fn new(window_size: (f64, f64)) -> Self {
+        InputState {
+            cursor: (0.0, 0.0),
+            window_size,
+            dragging: false,
+            touches: TouchTracker::new(),
+        }
+    }
+
+    pub fn touch_down(&mut self, id: u64, position: (f64, f64)) {
+        self.touches.down(id, position);
+    }
+
+    pub fn touch_up(&mut self, id: u64) {
+        self.touches.up(id);
+    }
+
+    /// Feeds a finger's new position in; applies any resulting pan/pinch
+    /// directly to `scene`.
+    pub fn touch_moved(&mut self, scene: &mut Scene, id: u64, position: (f64, f64)) {
+        match self.touches.moved(id, position) {
+            Some(TouchGesture::Pan { delta }) => apply_pan(scene, delta),
+            Some(TouchGesture::Pinch { centroid, factor }) => {
+                scene.zoom_toward(centroid, self.window_size, factor);
+            }
+            None => {}
+        }
+    }
+}
+
+/// A pan (one finger) or pinch-zoom (two fingers, zooming about the
+/// centroid between them) gesture recognized from raw touch deltas.
+enum TouchGesture {
+    Pan { delta: (f64, f64) },
+    Pinch { centroid: (f64, f64), factor: f32 },
+}
+
+struct TouchTracker {
+    points: HashMap<u64, (f64, f64)>,
+}
+
+impl TouchTracker {
+    fn new() -> Self {
+        TouchTracker { points: HashMap::new() }
+    }
+
+    fn down(&mut self, id: u64, position: (f64, f64)) {
+        self.points.insert(id, position);
+    }
+
+    fn up(&mut self, id: u64) {
+        self.points.remove(&id);
+    }
+
+    fn moved(&mut self, id: u64, position: (f64, f64)) -> Option<TouchGesture> {
+        let previous = self.points.get(&id).cloned();
+        self.points.insert(id, position);
+
+        match self.points.len() {
+            1 => {
+                let previous = previous?;
+                Some(TouchGesture::Pan {
+                    delta: (position.0 - previous.0, position.1 - previous.1),
+                })
+            }
+            2 => {
+                // Pinch: compare the other finger's fixed position against
+                // this finger's old and new position to get a distance
+                // ratio, and use the midpoint between both fingers' current
+                // positions as the zoom centroid.
+                let previous = previous?;
+                let other = self.points.iter().find(|&(&other_id, _)| other_id != id)?.1;
+
+                let old_dist = distance(previous, *other);
+                let new_dist = distance(position, *other);
+                if old_dist < 1.0 {
+                    return None;
+                }
+
+                let factor = (new_dist / old_dist) as f32;
+                let centroid = ((position.0 + other.0) / 2.0, (position.1 + other.1) / 2.0);
+
+                Some(TouchGesture::Pinch { centroid, factor })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Accumulates a screen-space drag `delta` into `scene.pan`, scaled by the
+/// current zoom so the dragged point tracks the cursor/finger.
+pub fn apply_pan(scene: &mut Scene, delta: (f64, f64)) {
+    // screen-space Y grows downward, world-space Y grows upward.
+    scene.pan[0] += delta.0 as f32 / scene.zoom;
+    scene.pan[1] -= delta.1 as f32 / scene.zoom;
+}
@@ -0,0 +1,126 @@
+extern crate cgmath;
+extern crate gfx;
+extern crate image;
+extern crate lyon;
+extern crate resvg;
+
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod render;
+pub mod texture;
+
+// An analytic GPU coverage rasterizer (decomposing fills into edges for a
+// compute shader to rasterize into a mask, composited as an alternative to
+// lyon's tessellated triangles) was prototyped but pulled before landing:
+// this example's classic `gfx-rs` OpenGL backend has no compute-dispatch
+// path to drive it, which left the prototype's ~280 lines and three shader
+// files unreachable from the render loop. Re-add it once a real dispatch
+// path (gfx-hal, wgpu, or raw GL) backs this example.
+
+use lyon::math::Point;
+use lyon::path::builder::{FlatPathBuilder, PathBuilder};
+use lyon::path::Path;
+use lyon::tessellation::geometry_builder::VertexConstructor;
+use lyon::tessellation::StrokeOptions;
+use resvg::tree;
+
+use render::{GpuFillVertex, UvBox};
+
+/// Color used whenever an SVG paint can't be represented by the renderer yet.
+pub const FALLBACK_COLOR: tree::Color = tree::Color {
+    red: 0,
+    green: 0,
+    blue: 0,
+};
+
+/// Converts a resvg path into a lyon `Path`, flattening it in the process.
+pub fn convert_path(p: &tree::Path) -> Path {
+    let mut builder = Path::builder();
+    for segment in p.segments.iter() {
+        match *segment {
+            tree::PathSegment::MoveTo { x, y } => {
+                builder.move_to(point(x, y));
+            }
+            tree::PathSegment::LineTo { x, y } => {
+                builder.line_to(point(x, y));
+            }
+            tree::PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                builder.cubic_bezier_to(point(x1, y1), point(x2, y2), point(x, y));
+            }
+            tree::PathSegment::ClosePath => {
+                builder.close();
+            }
+        }
+    }
+
+    builder.build()
+}
+
+/// Extracts the stroke color and lyon tessellation options from a resvg stroke.
+pub fn convert_stroke(s: &tree::Stroke) -> (tree::Color, StrokeOptions) {
+    let color = match s.paint {
+        tree::Paint::Color(c) => c,
+        _ => FALLBACK_COLOR,
+    };
+
+    (color, StrokeOptions::tolerance(0.01).with_line_width(s.width as f32))
+}
+
+fn point(x: f64, y: f64) -> Point {
+    Point::new(x as f32, y as f32)
+}
+
+/// Builds fill/stroke vertices, carrying both the flat fallback color and the
+/// untransformed path-space position so gradient fills can be resolved in the
+/// fragment shader. When `uv_box` is set, vertices also carry UV coordinates
+/// generated by mapping path-space XY into the texture/pattern's local box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VertexCtor {
+    pub color: [f32; 4],
+    pub uv_box: Option<UvBox>,
+}
+
+impl VertexCtor {
+    pub fn new(color: tree::Color, opacity: f32) -> Self {
+        VertexCtor {
+            color: [
+                color.red as f32 / 255.0,
+                color.green as f32 / 255.0,
+                color.blue as f32 / 255.0,
+                opacity,
+            ],
+            uv_box: None,
+        }
+    }
+
+    pub fn textured(opacity: f32, uv_box: UvBox) -> Self {
+        VertexCtor {
+            color: [1.0, 1.0, 1.0, opacity],
+            uv_box: Some(uv_box),
+        }
+    }
+}
+
+impl VertexConstructor<Point, GpuFillVertex> for VertexCtor {
+    fn new_vertex(&mut self, point: Point) -> GpuFillVertex {
+        let position = point.to_array();
+        let uv = match self.uv_box {
+            Some(ref bbox) => bbox.map(position),
+            None => [0.0, 0.0],
+        };
+
+        GpuFillVertex {
+            position,
+            local_position: position,
+            uv,
+            color: self.color,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 extern crate cgmath;
 extern crate gfx;
+extern crate gfx_device_gl;
 extern crate gfx_window_glutin;
 extern crate glutin;
 extern crate lyon;
@@ -7,18 +8,202 @@ extern crate resvg;
 
 extern crate svg_render;
 
+mod input;
+
+use std::collections::HashMap;
+
 use gfx::traits::{Device, FactoryExt};
 use glutin::GlContext;
 use lyon::tessellation::geometry_builder::{BuffersBuilder, VertexBuffers};
 use lyon::tessellation::{FillOptions, FillTessellator, StrokeTessellator};
 use resvg::tree::TreeExt;
 
-use svg_render::FALLBACK_COLOR;
-use svg_render::render::{self, fill_pipeline, ColorFormat, DepthFormat, Scene, VertexCtor};
-use svg_render::{convert_path, convert_stroke};
+use svg_render::render::{
+    self, fill_pipeline, build_gradient_lut_data, ColorFormat, DepthFormat, GpuFillVertex,
+    GradientConstants, GradientStop, Scene, SpreadMethod, TextureConstants, UvBox,
+};
+use svg_render::texture::{self, WrapMode};
+use svg_render::{convert_path, convert_stroke, VertexCtor, FALLBACK_COLOR};
+
+use input::InputState;
 
 const WINDOW_SIZE: f32 = 800.0;
 
+/// Creates the window + gfx device/factory, trying each sample count in
+/// `sample_counts` (highest first) until one succeeds. `0` requests a
+/// non-multisampled surface and always succeeds, so it should be last.
+fn create_msaa_window(
+    builder: glutin::WindowBuilder,
+    event_loop: &glutin::EventsLoop,
+    sample_counts: &[u16],
+) -> (
+    glutin::GlWindow,
+    gfx_device_gl::Device,
+    gfx_device_gl::Factory,
+    gfx::handle::RenderTargetView<gfx_device_gl::Resources, ColorFormat>,
+    gfx::handle::DepthStencilView<gfx_device_gl::Resources, DepthFormat>,
+) {
+    for (i, &samples) in sample_counts.iter().enumerate() {
+        let context = glutin::ContextBuilder::new()
+            .with_vsync(true)
+            .with_multisampling(samples);
+
+        // `GlWindow::new` surfaces an unsupported sample count as a real
+        // `CreationError`, so there's no need to probe via `catch_unwind`
+        // around `gfx_window_glutin::init` (fragile: it would hard-abort
+        // under `panic = "abort"`, and relies on context creation never
+        // leaving partial GL/OS state behind on unwind).
+        match glutin::GlWindow::new(builder.clone(), context, event_loop) {
+            Ok(window) => {
+                if samples > 0 {
+                    println!("Using {}x MSAA", samples);
+                }
+                return gfx_window_glutin::init_existing::<ColorFormat, DepthFormat>(window);
+            }
+            Err(_) if i + 1 < sample_counts.len() => {
+                println!("{}x MSAA unavailable, falling back", samples);
+                continue;
+            }
+            Err(e) => panic!("failed to create window: {:?}", e),
+        }
+    }
+
+    unreachable!("sample_counts must include a count that always succeeds, e.g. 0");
+}
+
+/// One gradient/solid/textured fill, batched so it can be uploaded and drawn
+/// in a single call.
+struct DrawGroup {
+    mesh: VertexBuffers<GpuFillVertex, u16>,
+    gradient: GradientConstants,
+    lut: Option<Vec<[u8; 4]>>,
+    image: Option<(Vec<u8>, WrapMode)>,
+}
+
+impl DrawGroup {
+    fn solid() -> Self {
+        DrawGroup {
+            mesh: VertexBuffers::new(),
+            gradient: GradientConstants::none(),
+            lut: None,
+            image: None,
+        }
+    }
+
+    fn textured(bytes: Vec<u8>, wrap: WrapMode) -> Self {
+        DrawGroup {
+            mesh: VertexBuffers::new(),
+            gradient: GradientConstants::none(),
+            lut: None,
+            image: Some((bytes, wrap)),
+        }
+    }
+}
+
+fn convert_spread(method: resvg::tree::SpreadMethod) -> SpreadMethod {
+    match method {
+        resvg::tree::SpreadMethod::Pad => SpreadMethod::Pad,
+        resvg::tree::SpreadMethod::Repeat => SpreadMethod::Repeat,
+        resvg::tree::SpreadMethod::Reflect => SpreadMethod::Reflect,
+    }
+}
+
+fn gradient_stops(stops: &[resvg::tree::Stop]) -> Vec<GradientStop> {
+    stops
+        .iter()
+        .map(|stop| GradientStop {
+            offset: stop.offset.value() as f32,
+            color: [
+                stop.color.red as f32 / 255.0,
+                stop.color.green as f32 / 255.0,
+                stop.color.blue as f32 / 255.0,
+                stop.opacity.value() as f32,
+            ],
+        })
+        .collect()
+}
+
+/// A pattern fill whose tile content is a single `<image>`: the common case
+/// for real-world SVGs that embed a bitmap. Patterns that tile arbitrary
+/// vector content aren't rendered (they fall back to `FALLBACK_COLOR`).
+fn pattern_image_bytes(rtree: &resvg::tree::Tree, pattern: &resvg::tree::Pattern) -> Option<Vec<u8>> {
+    let pattern_node = rtree.defs_by_id(&pattern.id)?;
+    pattern_node.children().find_map(|child| match *child.value() {
+        resvg::tree::NodeKind::Image(ref image) => image_bytes(&image.data),
+        _ => None,
+    })
+}
+
+fn image_bytes(data: &resvg::tree::ImageData) -> Option<Vec<u8>> {
+    match *data {
+        resvg::tree::ImageData::Raw(ref bytes) => Some(bytes.clone()),
+        resvg::tree::ImageData::External(ref path) => std::fs::read(path).ok(),
+    }
+}
+
+/// Returns the `DrawGroup` to tessellate into for `paint`, creating it on
+/// first use; paths sharing the same paint are batched under one key.
+fn group_for_paint<'a>(
+    rtree: &resvg::tree::Tree,
+    groups: &'a mut Vec<DrawGroup>,
+    keys: &mut HashMap<String, usize>,
+    paint: &resvg::tree::Paint,
+) -> &'a mut DrawGroup {
+    let key = match *paint {
+        resvg::tree::Paint::Color(_) => "solid".to_string(),
+        resvg::tree::Paint::LinearGradient(ref g) => format!("linear:{}", g.id),
+        resvg::tree::Paint::RadialGradient(ref g) => format!("radial:{}", g.id),
+        resvg::tree::Paint::Pattern(ref p) => format!("pattern:{}", p.id),
+    };
+
+    let index = *keys.entry(key).or_insert_with(|| {
+        let group = match *paint {
+            resvg::tree::Paint::Color(_) => DrawGroup::solid(),
+            resvg::tree::Paint::LinearGradient(ref g) => DrawGroup {
+                mesh: VertexBuffers::new(),
+                gradient: GradientConstants::linear(
+                    [g.x1 as f32, g.y1 as f32],
+                    [g.x2 as f32, g.y2 as f32],
+                    convert_spread(g.spread_method),
+                ),
+                lut: Some(build_gradient_lut_data(&gradient_stops(&g.stops))),
+                image: None,
+            },
+            resvg::tree::Paint::RadialGradient(ref g) => DrawGroup {
+                mesh: VertexBuffers::new(),
+                gradient: GradientConstants::radial(
+                    [g.cx as f32, g.cy as f32],
+                    g.r.value() as f32,
+                    convert_spread(g.spread_method),
+                ),
+                lut: Some(build_gradient_lut_data(&gradient_stops(&g.stops))),
+                image: None,
+            },
+            resvg::tree::Paint::Pattern(ref p) => match pattern_image_bytes(rtree, p) {
+                Some(bytes) => DrawGroup::textured(bytes, WrapMode::Tile),
+                None => DrawGroup::solid(),
+            },
+        };
+        groups.push(group);
+        groups.len() - 1
+    });
+
+    &mut groups[index]
+}
+
+/// Returns the path-space box that this paint's UVs should be mapped
+/// against: the pattern tile rect for pattern fills, or an identity box
+/// (UVs collapse to the vertex's own XY, unused by solid/gradient draws).
+fn uv_box_for_paint(paint: &resvg::tree::Paint) -> UvBox {
+    match *paint {
+        resvg::tree::Paint::Pattern(ref p) => UvBox {
+            min: [p.rect.x as f32, p.rect.y as f32],
+            size: [p.rect.width as f32, p.rect.height as f32],
+        },
+        _ => UvBox { min: [0.0, 0.0], size: [1.0, 1.0] },
+    }
+}
+
 fn main() {
     let args = std::env::args().collect::<Vec<_>>();
     if args.len() != 2 {
@@ -28,59 +213,124 @@ fn main() {
 
     let mut fill_tess = FillTessellator::new();
     let mut stroke_tess = StrokeTessellator::new();
-    let mut mesh = VertexBuffers::new();
 
     let opt = resvg::Options::default();
     let rtree = resvg::parse_rtree_from_file(&args[1], &opt).unwrap();
 
     let view_box = rtree.svg_node().view_box;
     let mut transform = None;
-    for node in rtree.root().descendants() {
-        if let resvg::tree::NodeKind::Path(ref p) = *node.value() {
-            // use the first transform component
-            if transform == None {
-                transform = Some(node.value().transform());
-            }
 
-            // get paint or create default one
-            let (paint, opacity) = match p.fill {
-                Some(f) => (f.paint, f.opacity),
-                None => (resvg::tree::Paint::Color(FALLBACK_COLOR), 1.0),
-            };
+    let mut groups: Vec<DrawGroup> = Vec::new();
+    let mut group_keys: HashMap<String, usize> = HashMap::new();
 
-            // fall back to always use color fill
-            // no gradients (yet?)
-            let color = match paint {
-                resvg::tree::Paint::Color(c) => c,
-                _ => FALLBACK_COLOR,
-            };
+    for node in rtree.root().descendants() {
+        match *node.value() {
+            resvg::tree::NodeKind::Path(ref p) => {
+                // use the first transform component
+                if transform == None {
+                    transform = Some(node.value().transform());
+                }
 
-            let _ = fill_tess
-                .tessellate_path(
-                    convert_path(p).path_iter(),
-                    &FillOptions::tolerance(0.01),
-                    &mut BuffersBuilder::new(&mut mesh, VertexCtor::new(color, opacity)),
-                )
-                .expect("Error during tesselation!");
-
-            if let Some(ref stroke) = p.stroke {
-                let (stroke_color, stroke_opts) = convert_stroke(stroke);
-                let opacity = stroke.opacity;
-                let _ = stroke_tess.tessellate_path(
-                    convert_path(p).path_iter(),
-                    &stroke_opts.with_tolerance(0.01),
-                    &mut BuffersBuilder::new(&mut mesh, VertexCtor::new(stroke_color, opacity)),
-                );
+                // get paint or create default one
+                let (paint, opacity) = match p.fill {
+                    Some(ref f) => (f.paint.clone(), f.opacity),
+                    None => (resvg::tree::Paint::Color(FALLBACK_COLOR), 1.0),
+                };
+
+                let color = match paint {
+                    resvg::tree::Paint::Color(c) => c,
+                    // the group's gradient/texture already carries the
+                    // color; the vertex only needs to pass opacity through.
+                    _ => FALLBACK_COLOR,
+                };
+
+                let group = group_for_paint(&rtree, &mut groups, &mut group_keys, &paint);
+
+                // Only emit textured UVs if the group actually resolved to an
+                // image: a pattern whose tile isn't a supported `<image>`
+                // falls back to a solid `DrawGroup` (see `group_for_paint`),
+                // and must fall back to `FALLBACK_COLOR` here too, or it'd
+                // sample `t_color` on a group that never binds a texture.
+                let vertex_ctor = match group.image {
+                    Some(_) => VertexCtor::textured(opacity, uv_box_for_paint(&paint)),
+                    None => VertexCtor::new(color, opacity),
+                };
+
+                let _ = fill_tess
+                    .tessellate_path(
+                        convert_path(p).path_iter(),
+                        &FillOptions::tolerance(0.01),
+                        &mut BuffersBuilder::new(&mut group.mesh, vertex_ctor),
+                    )
+                    .expect("Error during tesselation!");
+
+                if let Some(ref stroke) = p.stroke {
+                    let (stroke_color, stroke_opts) = convert_stroke(stroke);
+                    let opacity = stroke.opacity;
+                    // strokes always render as flat color, grouped with the solid batch
+                    let solid_group = group_for_paint(
+                        &rtree,
+                        &mut groups,
+                        &mut group_keys,
+                        &resvg::tree::Paint::Color(stroke_color),
+                    );
+                    let _ = stroke_tess.tessellate_path(
+                        convert_path(p).path_iter(),
+                        &stroke_opts.with_tolerance(0.01),
+                        &mut BuffersBuilder::new(&mut solid_group.mesh, VertexCtor::new(stroke_color, opacity)),
+                    );
+                }
+            }
+            resvg::tree::NodeKind::Image(ref image) => {
+                // standalone `<image>` elements: a single textured quad
+                // spanning the image's view box.
+                if let Some(bytes) = image_bytes(&image.data) {
+                    let rect = image.view_box.rect;
+                    let uv_box = UvBox {
+                        min: [rect.x as f32, rect.y as f32],
+                        size: [rect.width as f32, rect.height as f32],
+                    };
+
+                    // Unlike gradients/patterns, a bare `<image>` isn't
+                    // reachable via `url(#...)` so it's rarely given an id;
+                    // batch each one under its own group rather than keying
+                    // on `node.id()`, which collapses every unlabeled image
+                    // onto the same (empty-id) group and texture.
+                    groups.push(DrawGroup::textured(bytes, WrapMode::Clamp));
+                    let group = groups.last_mut().unwrap();
+
+                    let mut quad = lyon::path::Path::builder();
+                    quad.move_to(lyon::math::point(rect.x as f32, rect.y as f32));
+                    quad.line_to(lyon::math::point((rect.x + rect.width) as f32, rect.y as f32));
+                    quad.line_to(lyon::math::point(
+                        (rect.x + rect.width) as f32,
+                        (rect.y + rect.height) as f32,
+                    ));
+                    quad.line_to(lyon::math::point(rect.x as f32, (rect.y + rect.height) as f32));
+                    quad.close();
+
+                    let _ = fill_tess
+                        .tessellate_path(
+                            quad.build().path_iter(),
+                            &FillOptions::tolerance(0.01),
+                            &mut BuffersBuilder::new(&mut group.mesh, VertexCtor::textured(1.0, uv_box)),
+                        )
+                        .expect("Error during tesselation!");
+                }
             }
+            _ => {}
         }
     }
 
+    let total_vertices: usize = groups.iter().map(|g| g.mesh.vertices.len()).sum();
+    let total_indices: usize = groups.iter().map(|g| g.mesh.indices.len()).sum();
     println!(
-        "Finished tesselation: {} vertices, {} indices",
-        mesh.vertices.len(),
-        mesh.indices.len()
+        "Finished tesselation: {} vertices, {} indices in {} draw group(s)",
+        total_vertices,
+        total_indices,
+        groups.len()
     );
-    println!("Use arrow keys to pan, quare brackes to zoom.");
+    println!("Use arrow keys or left-drag to pan, square brackets or the scroll wheel to zoom.");
 
     // get svg view box parameters
     let vb_width = view_box.size.width as f32;
@@ -117,52 +367,166 @@ fn main() {
         .with_decorations(true)
         .with_title("SVG Renderer");
 
-    let context = glutin::ContextBuilder::new().with_vsync(true);
-
+    // Request a multisampled surface so tessellated edges aren't jagged;
+    // fall back to progressively fewer samples (then none) on platforms
+    // that can't satisfy the request.
     let (window, mut device, mut factory, mut main_fbo, mut main_depth) =
-        gfx_window_glutin::init::<ColorFormat, DepthFormat>(glutin_builder, context, &event_loop);
+        create_msaa_window(glutin_builder, &event_loop, &[8, 4, 0]);
 
+    #[cfg(not(feature = "hot-reload"))]
     let shader = factory
         .link_program(
             render::VERTEX_SHADER.as_bytes(),
             render::FRAGMENT_SHADER.as_bytes(),
         )
         .unwrap();
+    #[cfg(feature = "hot-reload")]
+    let shader = factory
+        .link_program(
+            &std::fs::read(render::VERTEX_SHADER_PATH).unwrap(),
+            &std::fs::read(render::FRAGMENT_SHADER_PATH).unwrap(),
+        )
+        .unwrap();
 
-    let pso = factory
+    let mut pso = factory
         .create_pipeline_from_program(
             &shader,
             gfx::Primitive::TriangleList,
-            gfx::state::Rasterizer::new_fill(),
+            render::fill_rasterizer(),
             fill_pipeline::new(),
         )
         .unwrap();
 
-    let (vbo, ibo) = factory.create_vertex_buffer_with_slice(&mesh.vertices[..], &mesh.indices[..]);
+    #[cfg(feature = "hot-reload")]
+    let mut shader_watcher =
+        svg_render::hot_reload::ShaderWatcher::new(render::VERTEX_SHADER_PATH, render::FRAGMENT_SHADER_PATH);
+
+    // one GPU-side draw binding per batched paint: vertex/index buffers, the
+    // gradient uniform block and LUT (for gradients), and the bound texture
+    // and sampler (for textured/pattern fills).
+    let draws: Vec<_> = groups
+        .iter()
+        .map(|group| {
+            let (vbo, ibo) =
+                factory.create_vertex_buffer_with_slice(&group.mesh.vertices[..], &group.mesh.indices[..]);
+            let gradient_buf = factory.create_constant_buffer(1);
+            let texture_flag_buf = factory.create_constant_buffer(1);
+
+            let lut = match group.lut {
+                Some(ref texels) => {
+                    let (_, view) = factory
+                        .create_texture_immutable::<ColorFormat>(
+                            gfx::texture::Kind::D2(
+                                render::GRADIENT_LUT_SIZE as u16,
+                                1,
+                                gfx::texture::AaMode::Single,
+                            ),
+                            gfx::texture::Mipmap::Provided,
+                            &[texels],
+                        )
+                        .unwrap();
+                    view
+                }
+                None => {
+                    let (_, view) = factory
+                        .create_texture_immutable::<ColorFormat>(
+                            gfx::texture::Kind::D2(1, 1, gfx::texture::AaMode::Single),
+                            gfx::texture::Mipmap::Provided,
+                            &[&[[255u8, 255, 255, 255]]],
+                        )
+                        .unwrap();
+                    view
+                }
+            };
+            let lut_sampler = factory.create_sampler_linear();
+
+            let (tex_color, tex_sampler, texture_flag) = match group.image {
+                Some((ref bytes, wrap)) => match texture::load_texture(&mut factory, bytes, wrap) {
+                    Ok((view, sampler)) => (view, sampler, TextureConstants::textured()),
+                    // An unsupported or corrupt embedded image shouldn't take
+                    // down the whole renderer; fall back the same way an
+                    // unsupported pattern tile does (see `pattern_image_bytes`).
+                    Err(e) => {
+                        println!("{}, falling back to FALLBACK_COLOR", e);
+                        let (view, sampler) = texture::fallback_texture(&mut factory);
+                        (view, sampler, TextureConstants::textured())
+                    }
+                },
+                None => {
+                    let (view, sampler) = texture::placeholder_texture(&mut factory);
+                    (view, sampler, TextureConstants::none())
+                }
+            };
+
+            (
+                vbo,
+                ibo,
+                gradient_buf,
+                lut,
+                lut_sampler,
+                group.gradient,
+                texture_flag_buf,
+                tex_color,
+                tex_sampler,
+                texture_flag,
+            )
+        })
+        .collect();
+
+    let mut input_state = InputState::new((width as f64, height as f64));
 
     let mut cmd_queue: gfx::Encoder<_, _> = factory.create_command_buffer().into();
 
     let constants = factory.create_constant_buffer(1);
 
     loop {
-        if !update_inputs(&mut scene, &mut event_loop) {
+        if !update_inputs(&mut scene, &mut input_state, &mut event_loop) {
             break;
         }
 
+        #[cfg(feature = "hot-reload")]
+        {
+            if shader_watcher.poll_changed() {
+                shader_watcher.try_relink(&mut factory, &mut pso);
+            }
+        }
+
         gfx_window_glutin::update_views(&window, &mut main_fbo, &mut main_depth);
 
         cmd_queue.clear(&main_fbo.clone(), [1.0, 1.0, 1.0, 1.0]);
+        cmd_queue.update_constant_buffer(&constants, &(&scene).into());
+
+        for &(
+            ref vbo,
+            ref ibo,
+            ref gradient_buf,
+            ref lut,
+            ref lut_sampler,
+            gradient,
+            ref texture_flag_buf,
+            ref tex_color,
+            ref tex_sampler,
+            texture_flag,
+        ) in &draws
+        {
+            cmd_queue.update_constant_buffer(gradient_buf, &gradient);
+            cmd_queue.update_constant_buffer(texture_flag_buf, &texture_flag);
+            cmd_queue.draw(
+                ibo,
+                &pso,
+                &fill_pipeline::Data {
+                    vbo: vbo.clone(),
+                    constants: constants.clone(),
+                    gradient: gradient_buf.clone(),
+                    gradient_lut: (lut.clone(), lut_sampler.clone()),
+                    texture_flag: texture_flag_buf.clone(),
+                    tex_color: (tex_color.clone(), tex_sampler.clone()),
+                    out_color: main_fbo.clone(),
+                    out_depth: main_depth.clone(),
+                },
+            );
+        }
 
-        cmd_queue.update_constant_buffer(&constants, &scene.into());
-        cmd_queue.draw(
-            &ibo,
-            &pso,
-            &fill_pipeline::Data {
-                vbo: vbo.clone(),
-                out_color: main_fbo.clone(),
-                constants: constants.clone(),
-            },
-        );
         cmd_queue.flush(&mut device);
 
         window.swap_buffers().unwrap();
@@ -171,7 +535,7 @@ fn main() {
     }
 }
 
-fn update_inputs(scene: &mut Scene, event_loop: &mut glutin::EventsLoop) -> bool {
+fn update_inputs(scene: &mut Scene, input: &mut InputState, event_loop: &mut glutin::EventsLoop) -> bool {
     use glutin::Event;
     use glutin::VirtualKeyCode;
     use glutin::ElementState::Pressed;
@@ -190,9 +554,54 @@ fn update_inputs(scene: &mut Scene, event_loop: &mut glutin::EventsLoop) -> bool
             event: glutin::WindowEvent::Resized(w, h),
             ..
         } => {
+            input.window_size = (w as f64, h as f64);
             let scl = w as f32 / h as f32;
             scene.update_proj(cgmath::ortho(-scl, scl, -1.0, 1.0, -1.0, 1.0));
         }
+        Event::WindowEvent {
+            event: glutin::WindowEvent::MouseInput {
+                state,
+                button: glutin::MouseButton::Left,
+                ..
+            },
+            ..
+        } => {
+            input.dragging = state == Pressed;
+        }
+        Event::WindowEvent {
+            event: glutin::WindowEvent::CursorMoved { position, .. },
+            ..
+        } => {
+            if input.dragging {
+                let delta = (position.0 - input.cursor.0, position.1 - input.cursor.1);
+                input::apply_pan(scene, delta);
+            }
+            input.cursor = position;
+        }
+        Event::WindowEvent {
+            event: glutin::WindowEvent::MouseWheel { delta, .. },
+            ..
+        } => {
+            let notches = match delta {
+                glutin::MouseScrollDelta::LineDelta(_, y) => y,
+                glutin::MouseScrollDelta::PixelDelta(_, y) => y / 16.0,
+            };
+            let factor = 1.1f32.powf(notches);
+            scene.zoom_toward(input.cursor, input.window_size, factor);
+        }
+        Event::WindowEvent {
+            event: glutin::WindowEvent::Touch(glutin::Touch { phase, location, id, .. }),
+            ..
+        } => {
+            // single-finger drag pans; two fingers pinch-zoom about their
+            // centroid. `InputState` tracks active touch points so this
+            // works the same on any touchscreen glutin reports events for.
+            match phase {
+                glutin::TouchPhase::Started => input.touch_down(id, location),
+                glutin::TouchPhase::Moved => input.touch_moved(scene, id, location),
+                glutin::TouchPhase::Ended | glutin::TouchPhase::Cancelled => input.touch_up(id),
+            }
+        }
         Event::WindowEvent {
             event:
                 glutin::WindowEvent::KeyboardInput {
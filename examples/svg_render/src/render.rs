@@ -0,0 +1,255 @@
+use cgmath::{Matrix4, SquareMatrix};
+use gfx;
+
+pub type ColorFormat = gfx::format::Srgba8;
+pub type DepthFormat = gfx::format::DepthStencil;
+
+/// Rasterizer state for the fill PSO: fill mode with multisampling enabled,
+/// which is a no-op when bound to a non-multisampled render target.
+pub fn fill_rasterizer() -> gfx::state::Rasterizer {
+    gfx::state::Rasterizer::new_fill().with_samples()
+}
+
+/// Number of texels sampled along a gradient's stop axis.
+pub const GRADIENT_LUT_SIZE: usize = 256;
+
+/// Selects which formula the fragment shader uses to turn a fragment's
+/// position into a `t` parameter along the gradient LUT. Numbered to match
+/// `u_gradient_kind` in `shaders/fill.frag`, where 0 is reserved for "no
+/// gradient" (see `GradientConstants::none`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GradientKind {
+    Linear = 1,
+    Radial = 2,
+}
+
+/// How `t` wraps once a fragment falls outside `[0, 1]`, mirroring SVG's
+/// `spreadMethod`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpreadMethod {
+    Pad = 0,
+    Repeat = 1,
+    Reflect = 2,
+}
+
+gfx_defines! {
+    vertex GpuFillVertex {
+        position: [f32; 2] = "a_position",
+        local_position: [f32; 2] = "a_local_position",
+        uv: [f32; 2] = "a_uv",
+        color: [f32; 4] = "a_color",
+    }
+
+    constant Constants {
+        mvp: [[f32; 4]; 4] = "u_mvp",
+    }
+
+    constant GradientConstants {
+        // GradientKind as i32; 0 = no gradient (plain vertex color), 1 = linear, 2 = radial.
+        kind: i32 = "u_gradient_kind",
+        spread: i32 = "u_spread_method",
+        p0: [f32; 2] = "u_p0",
+        p1: [f32; 2] = "u_p1",
+        radius: f32 = "u_radius",
+        _pad: f32 = "u_pad",
+    }
+
+    constant TextureConstants {
+        // Whether this draw samples `t_color` instead of using the vertex
+        // color / gradient LUT. Kept separate from `GradientConstants` since
+        // a textured fill never also needs the gradient math.
+        use_texture: i32 = "u_use_texture",
+    }
+
+    pipeline fill_pipeline {
+        vbo: gfx::VertexBuffer<GpuFillVertex> = (),
+        constants: gfx::ConstantBuffer<Constants> = "Constants",
+        gradient: gfx::ConstantBuffer<GradientConstants> = "Gradient",
+        gradient_lut: gfx::TextureSampler<[f32; 4]> = "t_gradient_lut",
+        texture_flag: gfx::ConstantBuffer<TextureConstants> = "TextureFlag",
+        tex_color: gfx::TextureSampler<[f32; 4]> = "t_color",
+        out_color: gfx::RenderTarget<ColorFormat> = "out_color",
+        out_depth: gfx::DepthTarget<DepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
+}
+
+impl TextureConstants {
+    pub fn none() -> Self {
+        TextureConstants { use_texture: 0 }
+    }
+
+    pub fn textured() -> Self {
+        TextureConstants { use_texture: 1 }
+    }
+}
+
+impl GradientConstants {
+    pub fn none() -> Self {
+        GradientConstants {
+            kind: 0,
+            spread: SpreadMethod::Pad as i32,
+            p0: [0.0, 0.0],
+            p1: [0.0, 0.0],
+            radius: 0.0,
+            _pad: 0.0,
+        }
+    }
+
+    pub fn linear(p0: [f32; 2], p1: [f32; 2], spread: SpreadMethod) -> Self {
+        GradientConstants {
+            kind: GradientKind::Linear as i32,
+            spread: spread as i32,
+            p0,
+            p1,
+            radius: 0.0,
+            _pad: 0.0,
+        }
+    }
+
+    pub fn radial(center: [f32; 2], radius: f32, spread: SpreadMethod) -> Self {
+        GradientConstants {
+            kind: GradientKind::Radial as i32,
+            spread: spread as i32,
+            p0: center,
+            p1: [0.0, 0.0],
+            radius,
+            _pad: 0.0,
+        }
+    }
+}
+
+/// Path-space bounding box that a textured/pattern fill's vertices are
+/// mapped into to produce `[0, 1]` UV coordinates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UvBox {
+    pub min: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl UvBox {
+    pub fn map(&self, position: [f32; 2]) -> [f32; 2] {
+        [
+            (position[0] - self.min[0]) / self.size[0].max(1e-6),
+            (position[1] - self.min[1]) / self.size[1].max(1e-6),
+        ]
+    }
+}
+
+/// A single color stop of a linear/radial gradient, as read from the SVG.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+/// Resamples `stops` (sorted by `offset`) into a `GRADIENT_LUT_SIZE`-texel
+/// RGBA row, ready to be uploaded as a 1D (here: `size x 1`) LUT texture.
+pub fn build_gradient_lut_data(stops: &[GradientStop]) -> Vec<[u8; 4]> {
+    let mut texels = vec![[0u8; 4]; GRADIENT_LUT_SIZE];
+
+    if stops.is_empty() {
+        return texels;
+    }
+
+    for (i, texel) in texels.iter_mut().enumerate() {
+        let t = i as f32 / (GRADIENT_LUT_SIZE - 1) as f32;
+
+        // Find the stop pair straddling `t` and lerp between them.
+        let mut lo = stops[0];
+        let mut hi = stops[stops.len() - 1];
+        for window in stops.windows(2) {
+            if t >= window[0].offset && t <= window[1].offset {
+                lo = window[0];
+                hi = window[1];
+                break;
+            }
+        }
+
+        let span = (hi.offset - lo.offset).max(1e-6);
+        let local_t = ((t - lo.offset) / span).min(1.0).max(0.0);
+
+        let mut c = [0u8; 4];
+        for channel in 0..4 {
+            let value = lo.color[channel] + (hi.color[channel] - lo.color[channel]) * local_t;
+            c[channel] = (value.min(1.0).max(0.0) * 255.0) as u8;
+        }
+        *texel = c;
+    }
+
+    texels
+}
+
+/// Builds the model-view-projection matrix uploaded to the `Constants`
+/// uniform block from the scene's zoom/pan and the window's projection.
+fn build_mvp(zoom: f32, pan: [f32; 2], proj: Matrix4<f32>) -> [[f32; 4]; 4] {
+    let transform =
+        Matrix4::from_nonuniform_scale(zoom, zoom, 1.0) * Matrix4::from_translation(cgmath::vec3(pan[0], pan[1], 0.0));
+    (proj * transform).into()
+}
+
+#[derive(Debug)]
+pub struct Scene {
+    pub zoom: f32,
+    pub pan: [f32; 2],
+    pub proj: Matrix4<f32>,
+}
+
+impl Scene {
+    pub fn new(zoom: f32, pan: [f32; 2], proj: Matrix4<f32>) -> Self {
+        Scene { zoom, pan, proj }
+    }
+
+    pub fn update_proj(&mut self, proj: Matrix4<f32>) {
+        self.proj = proj;
+    }
+
+    /// Converts a cursor position in physical pixels (origin top-left) into
+    /// the same path-space coordinates vertices are authored in, inverting
+    /// the projection/zoom/pan the fragment otherwise goes through.
+    pub fn screen_to_world(&self, cursor: (f64, f64), window_size: (f64, f64)) -> [f32; 2] {
+        let ndc_x = (2.0 * cursor.0 / window_size.0 - 1.0) as f32;
+        let ndc_y = (1.0 - 2.0 * cursor.1 / window_size.1) as f32;
+
+        let inv_proj = self.proj.invert().unwrap_or_else(Matrix4::identity);
+        let pre_zoom = inv_proj * cgmath::Vector4::new(ndc_x, ndc_y, 0.0, 1.0);
+
+        [
+            pre_zoom.x / self.zoom - self.pan[0],
+            pre_zoom.y / self.zoom - self.pan[1],
+        ]
+    }
+
+    /// Zooms by `factor` (> 1 zooms in) while keeping the world point under
+    /// `cursor` fixed on screen.
+    pub fn zoom_toward(&mut self, cursor: (f64, f64), window_size: (f64, f64), factor: f32) {
+        let world_cursor = self.screen_to_world(cursor, window_size);
+
+        self.zoom *= factor;
+
+        let inv_factor = 1.0 / factor;
+        self.pan[0] = inv_factor * (world_cursor[0] + self.pan[0]) - world_cursor[0];
+        self.pan[1] = inv_factor * (world_cursor[1] + self.pan[1]) - world_cursor[1];
+    }
+}
+
+impl<'a> From<&'a Scene> for Constants {
+    fn from(scene: &'a Scene) -> Self {
+        Constants {
+            mvp: build_mvp(scene.zoom, scene.pan, scene.proj),
+        }
+    }
+}
+
+// In release builds the GLSL is embedded at compile time. Under the
+// `hot-reload` feature it's instead read from disk each time
+// `hot_reload::ShaderWatcher` detects the files changed, so shader tweaks
+// can be iterated without restarting the renderer; see `hot_reload`.
+#[cfg(not(feature = "hot-reload"))]
+pub const VERTEX_SHADER: &str = include_str!("../shaders/fill.vert");
+#[cfg(not(feature = "hot-reload"))]
+pub const FRAGMENT_SHADER: &str = include_str!("../shaders/fill.frag");
+
+#[cfg(feature = "hot-reload")]
+pub const VERTEX_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/fill.vert");
+#[cfg(feature = "hot-reload")]
+pub const FRAGMENT_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/fill.frag");
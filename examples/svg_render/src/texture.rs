@@ -0,0 +1,88 @@
+//! Loading raster images (`<image>` elements and pattern fills) into gfx
+//! textures for the textured-fill draw path.
+
+use gfx;
+use gfx::format::Rgba8;
+use gfx::texture as gfx_texture;
+use image;
+
+pub type TextureHandle<R> = gfx::handle::ShaderResourceView<R, [f32; 4]>;
+
+/// How a texture samples outside `[0, 1]`: `Clamp` for standalone `<image>`
+/// fills, `Tile` for `pattern` fills, which repeat across the fill region.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WrapMode {
+    Clamp,
+    Tile,
+}
+
+impl WrapMode {
+    fn to_gfx(self) -> gfx_texture::WrapMode {
+        match self {
+            WrapMode::Clamp => gfx_texture::WrapMode::Clamp,
+            WrapMode::Tile => gfx_texture::WrapMode::Tile,
+        }
+    }
+}
+
+/// Decodes `bytes` (PNG/JPEG, anything the `image` crate recognizes) and
+/// uploads it as an immutable RGBA8 texture plus a sampler using `wrap`.
+pub fn load_texture<F, R>(
+    factory: &mut F,
+    bytes: &[u8],
+    wrap: WrapMode,
+) -> Result<(TextureHandle<R>, gfx::handle::Sampler<R>), String>
+where
+    F: gfx::Factory<R>,
+    R: gfx::Resources,
+{
+    let img = image::load_from_memory(bytes)
+        .map_err(|e| format!("failed to decode image: {}", e))?
+        .to_rgba();
+
+    let (width, height) = img.dimensions();
+    let data = img.into_raw();
+
+    let kind = gfx_texture::Kind::D2(width as u16, height as u16, gfx_texture::AaMode::Single);
+    let (_, view) = factory
+        .create_texture_immutable_u8::<Rgba8>(kind, gfx_texture::Mipmap::Provided, &[&data])
+        .map_err(|e| format!("failed to upload texture: {:?}", e))?;
+
+    let sampler_info = gfx_texture::SamplerInfo::new(gfx_texture::FilterMethod::Bilinear, wrap.to_gfx());
+    let sampler = factory.create_sampler(sampler_info);
+
+    Ok((view, sampler))
+}
+
+/// A 1x1 opaque white texture, bound whenever a draw group doesn't use a
+/// texture so `fill_pipeline::Data` always has a valid `tex_color` slot.
+pub fn placeholder_texture<F, R>(factory: &mut F) -> (TextureHandle<R>, gfx::handle::Sampler<R>)
+where
+    F: gfx::Factory<R>,
+    R: gfx::Resources,
+{
+    let kind = gfx_texture::Kind::D2(1, 1, gfx_texture::AaMode::Single);
+    let (_, view) = factory
+        .create_texture_immutable_u8::<Rgba8>(kind, gfx_texture::Mipmap::Provided, &[&[255, 255, 255, 255]])
+        .unwrap();
+    let sampler = factory.create_sampler_linear();
+    (view, sampler)
+}
+
+/// A 1x1 opaque black texture (matching `svg_render::FALLBACK_COLOR`), bound
+/// in place of a texture whose source bytes failed to decode. The group's
+/// `texture_flag` stays `textured()`, so the fragment shader still samples
+/// `t_color` over the already-generated UVs, multiplying this flat color by
+/// `v_color.a` exactly as it would `FALLBACK_COLOR` on the solid-fill path.
+pub fn fallback_texture<F, R>(factory: &mut F) -> (TextureHandle<R>, gfx::handle::Sampler<R>)
+where
+    F: gfx::Factory<R>,
+    R: gfx::Resources,
+{
+    let kind = gfx_texture::Kind::D2(1, 1, gfx_texture::AaMode::Single);
+    let (_, view) = factory
+        .create_texture_immutable_u8::<Rgba8>(kind, gfx_texture::Mipmap::Provided, &[&[0, 0, 0, 255]])
+        .unwrap();
+    let sampler = factory.create_sampler_linear();
+    (view, sampler)
+}